@@ -2,43 +2,373 @@
 
 use bindgen::callbacks::{IntKind, ParseCallbacks};
 
+/// Prefixes of SQLite constant families that must always come out as `i32`
+/// (result/error codes, `sqlite3_open_v2` flags, `sqlite3_config` options),
+/// even for a value outside `i32` range that the value-based rule in
+/// `classify` would otherwise widen to `U64`/`I64`.
+const FORCE_I32_PREFIXES: &[&str] =
+    &["SQLITE_OK", "SQLITE_ERROR", "SQLITE_ABORT", "SQLITE_OPEN_", "SQLITE_CONFIG_", "SQLITE_IOCAP_"];
+
+/// Per-name overrides that pin a constant's [`IntKind`] regardless of the
+/// prefix-based rules above, for the rare macro that needs a one-off
+/// exception.
+const INT_KIND_OVERRIDES: &[(&str, IntKind)] = &[];
+
 #[derive(Debug)]
 struct SqliteTypeChooser;
 
-impl ParseCallbacks for SqliteTypeChooser {
-    fn int_macro(&self, _name: &str, value: i64) -> Option<IntKind> {
+impl SqliteTypeChooser {
+    /// Deterministically classifies the full SQLite constant space so the
+    /// generated bindings are identical regardless of host/target triple,
+    /// removing the need for the external `sed`-based `u32`->`i32` rewrite
+    /// some platform builds apply after the fact.
+    fn classify(&self, name: &str, value: i64) -> Option<IntKind> {
+        for (override_name, kind) in INT_KIND_OVERRIDES {
+            if *override_name == name {
+                return Some(*kind);
+            }
+        }
+        if FORCE_I32_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+            return Some(IntKind::I32);
+        }
+        // Anything not covered by an explicit override above is classified
+        // purely from its value's range, which already picks the correct
+        // wide type (`U64`/`I64`) for any macro that genuinely doesn't fit
+        // in `i32` - no separate "known wide macro" table is needed.
         if value >= i32::min_value() as i64 && value <= i32::max_value() as i64 {
             Some(IntKind::I32)
+        } else if value >= 0 {
+            Some(IntKind::U64)
         } else {
-            None
+            Some(IntKind::I64)
+        }
+    }
+}
+
+impl ParseCallbacks for SqliteTypeChooser {
+    fn int_macro(&self, name: &str, value: i64) -> Option<IntKind> {
+        self.classify(name, value)
+    }
+}
+
+/// The `SQLITE_ENABLE_*`/`SQLITE_*` preprocessor defines that a `bundled`
+/// build should compile `sqlite3.c` with, paired with the Cargo feature that
+/// turns each one on. This is the single source of truth consulted by both
+/// `build_bundled` (as `cc` defines) and `configure_builder` (as bindgen
+/// `clang_arg`s) so the compiled library and the generated bindings can
+/// never disagree about which symbols exist.
+const SQLITE_ENABLE_FLAGS: &[(&str, &str)] = &[
+    ("fts3", "SQLITE_ENABLE_FTS3"),
+    ("fts3_parenthesis", "SQLITE_ENABLE_FTS3_PARENTHESIS"),
+    ("fts5", "SQLITE_ENABLE_FTS5"),
+    ("rtree", "SQLITE_ENABLE_RTREE"),
+    ("json1", "SQLITE_ENABLE_JSON1"),
+    ("column_metadata", "SQLITE_ENABLE_COLUMN_METADATA"),
+    ("dbstat_vtab", "SQLITE_ENABLE_DBSTAT_VTAB"),
+    ("stat4", "SQLITE_ENABLE_STAT4"),
+    ("load_extension", "SQLITE_ENABLE_LOAD_EXTENSION"),
+    ("icu", "SQLITE_ENABLE_ICU"),
+];
+
+/// `SQLITE_ENABLE_*`/`SQLITE_*` defines that apply unconditionally whenever
+/// `bundled` is enabled, independent of any finer-grained feature.
+const SQLITE_BUNDLED_DEFAULT_DEFINES: &[&str] = &[
+    "SQLITE_DEFAULT_FOREIGN_KEYS=1",
+    "SQLITE_THREADSAFE=1",
+    "SQLITE_USE_URI",
+];
+
+/// Returns the `-D` defines that should apply to this build, derived from
+/// [`SQLITE_ENABLE_FLAGS`] and [`SQLITE_BUNDLED_DEFAULT_DEFINES`] filtered by
+/// which Cargo features are actually enabled.
+fn enabled_sqlite_defines() -> Vec<&'static str> {
+    let mut defines: Vec<&'static str> = Vec::new();
+    if cfg!(feature = "bundled") {
+        defines.extend_from_slice(SQLITE_BUNDLED_DEFAULT_DEFINES);
+    }
+    for (feature, define) in SQLITE_ENABLE_FLAGS {
+        if std::env::var(format!("CARGO_FEATURE_{}", feature.to_uppercase())).is_ok() {
+            defines.push(define);
+        }
+    }
+    defines
+}
+
+/// Applies the blocklist/callback configuration shared by both the live
+/// bindgen invocation and the `LIBSQLITE3_SYS_VERIFY_BINDINGS` drift check,
+/// so the two can never silently drift apart.
+fn configure_builder(mut builder: bindgen::Builder) -> bindgen::Builder {
+    builder = builder
+        .parse_callbacks(Box::new(SqliteTypeChooser))
+        .rustfmt_bindings(true)
+        .blocklist_function("sqlite3_vmprintf")
+        .blocklist_function("sqlite3_vsnprintf")
+        .blocklist_function("sqlite3_str_vappendf")
+        .blocklist_type("va_list")
+        .blocklist_type("__builtin_va_list")
+        .blocklist_type("__gnuc_va_list")
+        .blocklist_type("__va_list_tag")
+        .blocklist_item("__GNUC_VA_LIST");
+
+    if cfg!(feature = "unlock_notify") {
+        builder = builder.clang_arg("-DSQLITE_ENABLE_UNLOCK_NOTIFY");
+    }
+    if cfg!(feature = "preupdate_hook") {
+        builder = builder.clang_arg("-DSQLITE_ENABLE_PREUPDATE_HOOK");
+    }
+    if cfg!(feature = "session") {
+        builder = builder.clang_arg("-DSQLITE_ENABLE_SESSION");
+    }
+    for define in enabled_sqlite_defines() {
+        if cfg!(feature = "without-icu") && define == "SQLITE_ENABLE_ICU" {
+            continue;
+        }
+        builder = builder.clang_arg(format!("-D{}", define));
+    }
+
+    builder
+}
+
+/// Name of the static/dylib to link against, depending on whether `without-icu` drops ICU collation/normalization
+/// support for a smaller binary.
+fn link_lib_name() -> &'static str {
+    if cfg!(feature = "without-icu") {
+        "sqlite3_noicu"
+    } else {
+        "sqlite3"
+    }
+}
+
+/// Emits the `cargo:rustc-link-lib` directive for the system/prebuilt
+/// libsqlite3, choosing `static=` vs `dylib=` based on the `static-link`
+/// feature, and the ICU/no-ICU variant based on `without-icu`.
+fn link_sqlite() {
+    let lib = link_lib_name();
+    if cfg!(feature = "static-link") {
+        println!("cargo:rustc-link-lib=static={}", lib);
+    } else {
+        println!("cargo:rustc-link-lib=dylib={}", lib);
+    }
+}
+
+/// Compiles the vendored `sqlite3.c` amalgamation via the `cc` crate, using
+/// the exact same `SQLITE_ENABLE_*` defines that [`configure_builder`] feeds
+/// to bindgen, so the compiled symbols and the generated bindings agree.
+///
+/// This checkout does not vendor the amalgamation under `bundled/`
+/// (tracking a ~250k-line third-party source drop in this tree needs its
+/// own import step, not something build.rs can paper over); `bundled`
+/// fails fast below with that explained rather than at an opaque
+/// `cc::Build::compile` error once someone enables the feature.
+fn build_bundled() {
+    let amalgamation =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("bundled").join("sqlite3.c");
+    if !amalgamation.exists() {
+        panic!(
+            "the `bundled` feature requires a vendored SQLite amalgamation at {}, which this \
+             checkout does not include; import `sqlite3.c`/`sqlite3.h` from the matching SQLite \
+             {} release into bundled/ before building with this feature enabled",
+            amalgamation.display(),
+            PREBUILT_SQLITE_VERSION,
+        );
+    }
+    let mut cfg = cc::Build::new();
+    cfg.file(amalgamation).flag("-w");
+    for define in enabled_sqlite_defines() {
+        if cfg!(feature = "without-icu") && define == "SQLITE_ENABLE_ICU" {
+            continue;
+        }
+        match define.split_once('=') {
+            Some((key, value)) => {
+                cfg.define(key, value);
+            }
+            None => {
+                cfg.define(define, None);
+            }
+        }
+    }
+    cfg.compile(link_lib_name());
+}
+
+/// SQLite version the checked-in bindings under `prebuilt_bindings/` were
+/// generated against. Bump alongside the committed files when SQLite is
+/// upgraded.
+const PREBUILT_SQLITE_VERSION: &str = "3390400";
+
+fn target_arch() -> String {
+    std::env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Directory holding the checked-in bindings, one file per target arch +
+/// SQLite version, e.g. `prebuilt_bindings/aarch64-3390400.rs`. These are
+/// hand-written, not a full bindgen dump (see the file header comment for
+/// why), covering only the symbols this workspace's consumers call.
+/// Currently populated for `aarch64` and `x86_64`; add a file for any other
+/// target arch before building it with `buildtime_bindgen` disabled.
+fn prebuilt_bindings_path(target_arch: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("prebuilt_bindings")
+        .join(format!("{}-{}.rs", target_arch, PREBUILT_SQLITE_VERSION))
+}
+
+/// Runs bindgen with the exact same configuration as the normal build and
+/// returns the generated bindings as a string, without writing to disk.
+/// Used by the `LIBSQLITE3_SYS_VERIFY_BINDINGS` drift check below.
+fn generate_bindings_string() -> String {
+    configure_builder(bindgen::Builder::default())
+        .generate()
+        .expect("Unable to generate bindings")
+        .to_string()
+}
+
+/// Extracts the name of each top-level `pub fn`/`pub const`/`pub type`/
+/// `pub struct` declaration in `source`, in declaration order.
+fn declared_symbol_names(source: &str) -> Vec<&str> {
+    const PREFIXES: &[&str] = &["pub fn ", "pub const ", "pub type ", "pub struct "];
+    let mut names = Vec::new();
+    for line in source.lines() {
+        let line = line.trim();
+        for prefix in PREFIXES {
+            if let Some(rest) = line.strip_prefix(prefix) {
+                if let Some(name) = rest.split(|c: char| !(c.is_alphanumeric() || c == '_')).next()
+                {
+                    if !name.is_empty() {
+                        names.push(name);
+                    }
+                }
+                break;
+            }
+        }
+    }
+    names
+}
+
+/// True if `name` appears in `source` as a whole identifier, not merely as a
+/// substring of a longer one.
+fn contains_symbol(source: &str, name: &str) -> bool {
+    let bytes = source.as_bytes();
+    let mut start = 0;
+    while let Some(offset) = source[start..].find(name) {
+        let idx = start + offset;
+        let before_ok =
+            idx == 0 || !(bytes[idx - 1].is_ascii_alphanumeric() || bytes[idx - 1] == b'_');
+        let after = idx + name.len();
+        let after_ok =
+            after == bytes.len() || !(bytes[after].is_ascii_alphanumeric() || bytes[after] == b'_');
+        if before_ok && after_ok {
+            return true;
         }
+        start = idx + 1;
+    }
+    false
+}
+
+/// When `LIBSQLITE3_SYS_VERIFY_BINDINGS=1` is set, regenerate bindings with
+/// bindgen and fail the build if any symbol declared in the committed file
+/// is missing from a live run. This is the CI guard against silent ABI
+/// drift between the checked-in bindings and what bindgen would produce
+/// today.
+///
+/// The committed file is a hand-written, deliberately partial subset of
+/// sqlite3.h rather than a full bindgen dump, so this checks symbol
+/// coverage rather than byte-for-byte equality - an exact-content diff
+/// would always fail here, since full bindgen output always contains
+/// symbols this file doesn't declare.
+fn verify_prebuilt_bindings(committed: &str) {
+    if std::env::var("LIBSQLITE3_SYS_VERIFY_BINDINGS").as_deref() != Ok("1") {
+        return;
+    }
+    let generated = generate_bindings_string();
+    let missing: Vec<&str> = declared_symbol_names(committed)
+        .into_iter()
+        .filter(|name| !contains_symbol(&generated, name))
+        .collect();
+    if !missing.is_empty() {
+        panic!(
+            "symbols declared in the committed prebuilt bindings no longer appear in a live \
+             bindgen run (removed or renamed upstream?): {:?}; update prebuilt_bindings/ by hand \
+             to match the new sqlite3.h",
+            missing
+        );
     }
 }
 
+/// Offline path: copy the checked-in bindings for this target + SQLite
+/// version into `OUT_DIR`, optionally verifying they still match what
+/// bindgen would generate today.
+fn use_prebuilt_bindings() {
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    let arch = target_arch();
+    let src = prebuilt_bindings_path(&arch);
+    let committed = std::fs::read_to_string(&src).unwrap_or_else(|e| {
+        panic!(
+            "no prebuilt bindings for target_arch={} sqlite_version={} ({}): {}",
+            arch,
+            PREBUILT_SQLITE_VERSION,
+            src.display(),
+            e
+        )
+    });
+    verify_prebuilt_bindings(&committed);
+    std::fs::copy(&src, out_dir.join("bindings.rs")).expect("failed to copy prebuilt bindings");
+    println!("cargo:rerun-if-changed={}", src.display());
+    println!("cargo:rerun-if-env-changed=LIBSQLITE3_SYS_VERIFY_BINDINGS");
+}
+
+/// Writes `OUT_DIR/sqlite3_build_info.json`, a machine-readable record of
+/// the resolved SQLite version, the `SQLITE_ENABLE_*` defines actually
+/// applied, the link mode, and the bindgen version (the value of
+/// `DEP_BINDGEN_VERSION` when this build script was compiled, or `unknown`
+/// when that variable was not set), and sets the
+/// `cargo:rustc-cfg`/`cargo:rustc-env` values so downstream crates can
+/// `cfg!`-gate on capabilities like `unlock_notify`/`preupdate_hook`/
+/// `session` instead of guessing from Cargo features alone.
+fn emit_build_report() {
+    let link_mode = if cfg!(feature = "bundled") {
+        "bundled".to_string()
+    } else if cfg!(feature = "static-link") {
+        format!("static:{}", link_lib_name())
+    } else {
+        format!("dylib:{}", link_lib_name())
+    };
+
+    for capability in ["unlock_notify", "preupdate_hook", "session"] {
+        if std::env::var(format!("CARGO_FEATURE_{}", capability.to_uppercase())).is_ok() {
+            println!("cargo:rustc-cfg=libsqlite3_sys_{}", capability);
+        }
+    }
+    println!("cargo:rustc-env=LIBSQLITE3_SYS_VERSION={}", PREBUILT_SQLITE_VERSION);
+
+    let defines: Vec<&str> = enabled_sqlite_defines()
+        .into_iter()
+        .filter(|define| !(cfg!(feature = "without-icu") && *define == "SQLITE_ENABLE_ICU"))
+        .collect();
+    let defines_json: Vec<String> =
+        defines.iter().map(|d| format!("\"{}\"", d)).collect();
+    let manifest = format!(
+        "{{\n  \"sqlite_version\": \"{}\",\n  \"link_mode\": \"{}\",\n  \"bindgen_version\": \"{}\",\n  \"enabled_defines\": [{}]\n}}\n",
+        PREBUILT_SQLITE_VERSION,
+        link_mode,
+        option_env!("DEP_BINDGEN_VERSION").unwrap_or("unknown"),
+        defines_json.join(", "),
+    );
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    std::fs::write(out_dir.join("sqlite3_build_info.json"), manifest)
+        .expect("failed to write sqlite3_build_info.json");
+}
+
 fn main() {
-    bindgen_cmd::build(|mut builder| {
-        builder = builder
-            .parse_callbacks(Box::new(SqliteTypeChooser))
-            .rustfmt_bindings(true)
-            .blocklist_function("sqlite3_vmprintf")
-            .blocklist_function("sqlite3_vsnprintf")
-            .blocklist_function("sqlite3_str_vappendf")
-            .blocklist_type("va_list")
-            .blocklist_type("__builtin_va_list")
-            .blocklist_type("__gnuc_va_list")
-            .blocklist_type("__va_list_tag")
-            .blocklist_item("__GNUC_VA_LIST");
- 
-        if cfg!(feature = "unlock_notify") {
-            builder = builder.clang_arg("-DSQLITE_ENABLE_UNLOCK_NOTIFY");
-        }
-        if cfg!(feature = "preupdate_hook") {
-            builder = builder.clang_arg("-DSQLITE_ENABLE_PREUPDATE_HOOK");
-        }
-        if cfg!(feature = "session") {
-            builder = builder.clang_arg("-DSQLITE_ENABLE_SESSION");
-        }
-
-        builder
-    })
+    if cfg!(feature = "bundled") {
+        build_bundled();
+    } else {
+        link_sqlite();
+    }
+
+    if cfg!(feature = "buildtime_bindgen") {
+        bindgen_cmd::build(configure_builder)
+    } else {
+        use_prebuilt_bindings()
+    }
+
+    emit_build_report();
 }
@@ -0,0 +1,93 @@
+//! Per-session credit-based flow control for the UCI data-transfer path.
+//!
+//! The controller grants a limited number of transmit credits per session
+//! via a `SESSION_DATA_CREDIT_NTF`; each credit permits sending one data
+//! message. `send_data_message` blocks on [`CreditTracker::acquire`]
+//! instead of flooding the controller's receive buffer ahead of its
+//! advertised capacity.
+
+use super::gid_oid_of;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{Mutex, Notify};
+
+/// UCI Session Control group id.
+const SESSION_GID: u8 = 0x01;
+/// Opcode of the session data-credit availability notification within the
+/// session control group.
+const SESSION_DATA_CREDIT_NTF_OID: u8 = 0x07;
+
+/// If `raw` (an unfragmented, unparsed UCI packet) is a
+/// `SESSION_DATA_CREDIT_NTF`, returns the session id and number of credits
+/// it grants.
+pub(super) fn session_data_credit_ntf(raw: &[u8]) -> Option<(i32, u8)> {
+    if gid_oid_of(raw) != Some((SESSION_GID, SESSION_DATA_CREDIT_NTF_OID)) {
+        return None;
+    }
+    if raw.len() < 9 {
+        return None;
+    }
+    let session_id = i32::from_le_bytes(raw[4..8].try_into().ok()?);
+    let credits = raw[8];
+    Some((session_id, credits))
+}
+
+struct SessionCredit {
+    available: Mutex<u8>,
+    notify: Notify,
+}
+
+impl Default for SessionCredit {
+    fn default() -> Self {
+        SessionCredit { available: Mutex::new(0), notify: Notify::new() }
+    }
+}
+
+/// Tracks outstanding transmit credits, keyed by session id.
+#[derive(Default)]
+pub struct CreditTracker {
+    sessions: Mutex<HashMap<i32, Arc<SessionCredit>>>,
+}
+
+impl CreditTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn session(&self, session_id: i32) -> Arc<SessionCredit> {
+        self.sessions.lock().await.entry(session_id).or_insert_with(Arc::default).clone()
+    }
+
+    /// Records `credits` more available transmit slots for `session_id`.
+    pub async fn grant(&self, session_id: i32, credits: u8) {
+        let session = self.session(session_id).await;
+        {
+            let mut available = session.available.lock().await;
+            *available = available.saturating_add(credits);
+        }
+        session.notify.notify_waiters();
+    }
+
+    /// Waits until a transmit credit is available for `session_id`, then
+    /// consumes it.
+    pub async fn acquire(&self, session_id: i32) {
+        let session = self.session(session_id).await;
+        loop {
+            // Register as a waiter before checking the condition, not after:
+            // `grant` wakes waiters with `notify_waiters`, which (unlike
+            // `notify_one`) does not buffer a permit for a waiter that
+            // hasn't subscribed yet. Checking first and subscribing second
+            // leaves a gap where a `grant` lands after the failed check but
+            // before `notified()`, and is never observed.
+            let notified = session.notify.notified();
+            {
+                let mut available = session.available.lock().await;
+                if *available > 0 {
+                    *available -= 1;
+                    return;
+                }
+            }
+            notified.await;
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Human-readable disassembly of a raw UCI packet, in the spirit of
+//! yaxpeax-arm's disassembler-with-`Display` approach to turning raw
+//! instruction bytes into annotated text: walk the header, name the known
+//! group id / opcode id combinations, and fall back to a hex+ASCII dump for
+//! anything not recognized. Meant to make the inline byte arrays already
+//! used throughout this crate's tests self-documenting, and to give field
+//! engineers a quick way to inspect a captured frame.
+
+/// Message type, the 3 bits at the top of a UCI packet's first header byte.
+fn message_type_name(byte0: u8) -> &'static str {
+    match (byte0 >> 5) & 0x07 {
+        0 => "Data",
+        1 => "Command",
+        2 => "Response",
+        3 => "Notification",
+        _ => "Reserved",
+    }
+}
+
+fn group_id_name(gid: u8) -> &'static str {
+    match gid {
+        0x00 => "Core",
+        0x01 => "SessionControl",
+        0x02 => "RangingSessionControl",
+        0x0c => "AndroidVendor",
+        0x09..=0x0f => "Vendor",
+        _ => "Unknown",
+    }
+}
+
+/// Names the known (group id, opcode id) combinations this crate already
+/// decodes elsewhere in the adaptation layer.
+fn opcode_name(gid: u8, oid: u8) -> Option<&'static str> {
+    match (gid, oid) {
+        (0x00, 0x00) => Some("DEVICE_RESET"),
+        (0x00, 0x01) => Some("DEVICE_STATUS_NTF"),
+        (0x00, 0x02) => Some("GET_DEVICE_INFO"),
+        (0x00, 0x03) => Some("GET_CAPS_INFO"),
+        (0x00, 0x04) => Some("SET_CONFIG"),
+        (0x00, 0x05) => Some("GET_CONFIG"),
+        (0x00, 0x07) => Some("GENERIC_ERROR_NTF"),
+        (0x01, 0x00) => Some("SESSION_INIT"),
+        (0x01, 0x01) => Some("SESSION_DEINIT"),
+        (0x01, 0x02) => Some("SESSION_STATUS_NTF"),
+        (0x01, 0x07) => Some("SESSION_DATA_CREDIT_NTF"),
+        (0x02, 0x00) => Some("RANGE_START / RANGE_DATA_NTF"),
+        (0x02, 0x01) => Some("RANGE_STOP"),
+        _ => None,
+    }
+}
+
+/// Per-field breakdown for the small set of notification/command payloads
+/// this crate already parses by hand elsewhere (see
+/// `recovery::synthesize_device_status_error`, `credit::session_data_credit_ntf`).
+/// Returns `None` for anything not recognized, so the caller can fall back
+/// to a hex+ASCII dump.
+fn decode_known_fields(gid: u8, oid: u8, payload: &[u8]) -> Option<Vec<String>> {
+    match (gid, oid) {
+        (0x00, 0x01) if !payload.is_empty() => {
+            Some(vec![format!("device_state: 0x{:02x}", payload[0])])
+        }
+        (0x00, 0x07) if !payload.is_empty() => Some(vec![format!("status: 0x{:02x}", payload[0])]),
+        (0x01, 0x02) if payload.len() >= 6 => Some(vec![
+            format!(
+                "session_id: 0x{:08x}",
+                u32::from_le_bytes(payload[0..4].try_into().unwrap())
+            ),
+            format!("session_state: 0x{:02x}", payload[4]),
+            format!("reason_code: 0x{:02x}", payload[5]),
+        ]),
+        (0x01, 0x07) if payload.len() >= 5 => Some(vec![
+            format!(
+                "session_id: 0x{:08x}",
+                u32::from_le_bytes(payload[0..4].try_into().unwrap())
+            ),
+            format!("credits: {}", payload[4]),
+        ]),
+        _ => None,
+    }
+}
+
+/// Renders `data` as hex bytes with an ASCII gutter, 16 bytes per line, the
+/// same layout `xxd`/`hexdump -C` use.
+fn hex_ascii_dump(data: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in data.chunks(16) {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| if (0x20..0x7f).contains(&b) { b as char } else { '.' })
+            .collect();
+        out.push_str(&format!("    {:<47} {}\n", hex.join(" "), ascii));
+    }
+    out
+}
+
+/// Walks one raw (unfragmented) UCI packet and produces an annotated,
+/// human-readable dump: message type, group id, opcode, PBF/fragment flag,
+/// payload length, and a per-field breakdown for known notification and
+/// command types. Falls back to a hex+ASCII view of the payload for
+/// anything this crate doesn't otherwise decode.
+pub fn disassemble(bytes: &[u8]) -> String {
+    if bytes.len() < 4 {
+        return format!("truncated UCI packet ({} byte(s)):\n{}", bytes.len(), hex_ascii_dump(bytes));
+    }
+    let byte0 = bytes[0];
+    let gid = byte0 & 0x0f;
+    let oid = bytes[1] & 0x3f;
+    let pbf = (byte0 & 0x10) != 0;
+    let payload_len = bytes[3] as usize;
+    let payload = bytes.get(4..).unwrap_or(&[]);
+
+    let mut out = format!(
+        "MT={} GID=0x{:02x} ({}) OID=0x{:02x}{} PBF={} Length={}\n",
+        message_type_name(byte0),
+        gid,
+        group_id_name(gid),
+        oid,
+        opcode_name(gid, oid).map(|name| format!(" ({})", name)).unwrap_or_default(),
+        pbf as u8,
+        payload_len,
+    );
+
+    match decode_known_fields(gid, oid, payload) {
+        Some(fields) => {
+            for field in fields {
+                out.push_str("  ");
+                out.push_str(&field);
+                out.push('\n');
+            }
+        }
+        None if !payload.is_empty() => out.push_str(&hex_ascii_dump(payload)),
+        None => {}
+    }
+    out
+}
+
+/// Parses a whitespace-separated hex byte string (e.g. `"60 01 00 01 01"`),
+/// the input format the CLI entry point accepts on its command line.
+pub fn parse_hex_bytes(s: &str) -> Option<Vec<u8>> {
+    s.split_whitespace().map(|token| u8::from_str_radix(token, 16).ok()).collect()
+}
+
+/// Core logic of the `uci-disasm` CLI entry point (see `src/rust/bin/uci-disasm.rs`):
+/// joins its arguments into one hex string, parses it, and disassembles it.
+/// Kept free of process-global side effects (`std::env`/`std::process::exit`)
+/// so it can be exercised directly in tests.
+pub fn run_cli<I: IntoIterator<Item = String>>(args: I) -> String {
+    let joined = args.into_iter().collect::<Vec<_>>().join(" ");
+    match parse_hex_bytes(&joined) {
+        Some(bytes) => disassemble(&bytes),
+        None => format!("error: could not parse '{}' as whitespace-separated hex bytes", joined),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disassembles_device_status_ntf() {
+        let text = disassemble(&[0x60, 0x01, 0x00, 0x01, 0x01]);
+        assert!(text.contains("MT=Notification"));
+        assert!(text.contains("DEVICE_STATUS_NTF"));
+        assert!(text.contains("device_state: 0x01"));
+    }
+
+    #[test]
+    fn disassembles_session_status_ntf() {
+        let text = disassemble(&[0x61, 0x02, 0x00, 0x06, 0x01, 0x02, 0x03, 0x04, 0x02, 0x21]);
+        assert!(text.contains("SESSION_STATUS_NTF"));
+        assert!(text.contains("session_id: 0x04030201"));
+        assert!(text.contains("session_state: 0x02"));
+        assert!(text.contains("reason_code: 0x21"));
+    }
+
+    #[test]
+    fn falls_back_to_hex_dump_for_unknown_payload() {
+        let text = disassemble(&[0x49, 0x01, 0x00, 0x02, 0xaa, 0xbb]);
+        assert!(text.contains("aa bb"));
+    }
+
+    #[test]
+    fn run_cli_round_trips_hex_string() {
+        let text = run_cli(["60".to_string(), "01".to_string(), "00".to_string(), "01".to_string(), "01".to_string()]);
+        assert!(text.contains("DEVICE_STATUS_NTF"));
+    }
+
+    #[test]
+    fn run_cli_reports_unparseable_input() {
+        let text = run_cli(["not-hex".to_string()]);
+        assert!(text.starts_with("error:"));
+    }
+}
@@ -0,0 +1,173 @@
+//! Structured JSON event logging for decoded UCI notifications, modeled on
+//! Suricata's per-protocol `logger.rs` modules that serialize parsed
+//! protocol records to line-delimited JSON. Lets integrators build a
+//! ranging telemetry / post-mortem event stream without re-parsing UCI
+//! themselves.
+
+use crate::error::UwbErr;
+use crate::uci::uci_hrcv::UciNotification;
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+type Result<T> = std::result::Result<T, UwbErr>;
+
+/// Destination for serialized notification events. Implementations must be
+/// safe to call from the HAL callback path without blocking it for long.
+pub trait EventSink: Send + Sync {
+    fn write_event(&self, json_line: String);
+}
+
+/// Appends one JSON object per line to a file.
+pub struct FileEventSink {
+    file: Mutex<std::fs::File>,
+}
+
+impl FileEventSink {
+    pub fn open(path: &std::path::Path) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(FileEventSink { file: Mutex::new(file) })
+    }
+}
+
+impl EventSink for FileEventSink {
+    fn write_event(&self, json_line: String) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(file, "{}", json_line);
+        }
+    }
+}
+
+/// Keeps only the most recent `capacity` events in memory; useful for tests
+/// and other in-process consumers that don't want a file on disk.
+pub struct RingEventSink {
+    capacity: usize,
+    events: Mutex<VecDeque<String>>,
+}
+
+impl RingEventSink {
+    pub fn new(capacity: usize) -> Self {
+        RingEventSink { capacity, events: Mutex::new(VecDeque::with_capacity(capacity)) }
+    }
+
+    pub fn events(&self) -> Vec<String> {
+        self.events.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+impl EventSink for RingEventSink {
+    fn write_event(&self, json_line: String) {
+        let mut events = self.events.lock().unwrap();
+        if events.len() >= self.capacity {
+            events.pop_front();
+        }
+        events.push_back(json_line);
+    }
+}
+
+fn escape_json(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Builds the JSON line for one decoded notification, or `None` for variants
+/// that don't carry fields worth logging structurally (e.g. a multicast
+/// list update, already covered by the raw pcapng/tap capture).
+fn to_json_line(chip_id: &str, timestamp_ms: u128, ntf: &UciNotification) -> Option<String> {
+    let body = match ntf {
+        UciNotification::GenericError(evt) => {
+            format!(r#""kind":"generic_error","status":{}"#, u8::from(evt.get_status()))
+        }
+        UciNotification::DeviceStatusNtf(evt) => {
+            format!(r#""kind":"device_status","device_state":{}"#, u8::from(evt.get_device_state()))
+        }
+        UciNotification::SessionStatusNtf(evt) => format!(
+            r#""kind":"session_status","session_id":{},"session_state":{},"reason_code":{}"#,
+            evt.get_session_id(),
+            u8::from(evt.get_session_state()),
+            u8::from(evt.get_reason_code())
+        ),
+        UciNotification::ShortMacTwoWayRangeDataNtf(evt) => format!(
+            r#""kind":"short_mac_two_way_range_data","session_id":{},"sequence_number":{},"measurements":[{}]"#,
+            evt.get_session_id(),
+            evt.get_sequence_number(),
+            evt.get_two_way_ranging_measurements()
+                .iter()
+                .map(|m| format!(
+                    r#"{{"mac_address":{},"status":{},"distance":{},"aoa_azimuth":{},"aoa_elevation":{}}}"#,
+                    m.mac_address,
+                    u8::from(m.status),
+                    m.distance,
+                    m.aoa_azimuth,
+                    m.aoa_elevation
+                ))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        UciNotification::ExtendedMacTwoWayRangeDataNtf(evt) => format!(
+            r#""kind":"extended_mac_two_way_range_data","session_id":{},"sequence_number":{},"measurements":[{}]"#,
+            evt.get_session_id(),
+            evt.get_sequence_number(),
+            evt.get_two_way_ranging_measurements()
+                .iter()
+                .map(|m| format!(
+                    r#"{{"mac_address":{},"status":{},"distance":{},"aoa_azimuth":{},"aoa_elevation":{}}}"#,
+                    m.mac_address,
+                    u8::from(m.status),
+                    m.distance,
+                    m.aoa_azimuth,
+                    m.aoa_elevation
+                ))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        UciNotification::RawVendorNtf(evt) => format!(
+            r#""kind":"raw_vendor","gid":{},"oid":{},"payload_hex":"{}""#,
+            evt.get_gid(),
+            evt.get_oid(),
+            hex(&evt.get_payload())
+        ),
+        _ => return None,
+    };
+    Some(format!(r#"{{"timestamp_ms":{},"chip_id":"{}",{}}}"#, timestamp_ms, escape_json(chip_id), body))
+}
+
+/// Serializes decoded `UciNotification`s to a pluggable [`EventSink`].
+pub struct NotificationEventLogger {
+    sink: Arc<dyn EventSink>,
+    start: Instant,
+}
+
+impl NotificationEventLogger {
+    pub fn new(sink: Arc<dyn EventSink>) -> Self {
+        NotificationEventLogger { sink, start: Instant::now() }
+    }
+
+    /// Serializes `ntf` and forwards it to the sink, if its variant carries
+    /// fields worth logging structurally.
+    pub fn log(&self, chip_id: &str, ntf: &UciNotification) {
+        let timestamp_ms = self.start.elapsed().as_millis();
+        if let Some(line) = to_json_line(chip_id, timestamp_ms, ntf) {
+            self.sink.write_event(line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_sink_drops_oldest_past_capacity() {
+        let sink = RingEventSink::new(2);
+        sink.write_event("a".to_string());
+        sink.write_event("b".to_string());
+        sink.write_event("c".to_string());
+        assert_eq!(sink.events(), vec!["b".to_string(), "c".to_string()]);
+    }
+}
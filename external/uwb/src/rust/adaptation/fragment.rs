@@ -0,0 +1,198 @@
+//! Bounded, timeout-guarded UCI fragment reassembly.
+//!
+//! A faulty or hostile HAL could emit an endless stream of non-terminating
+//! fragments (the packet-boundary-flag bit never cleared), or interleave
+//! fragments for a message that never completes, growing the reassembly
+//! buffer without limit. `FragmentReassembler` sits in front of the real
+//! defragmenter and gates fragments by GID/OID key, borrowing the
+//! stream-reassembly hardening approach used in Suricata's app-layer
+//! parsers: a maximum total reassembled byte count, a maximum fragment
+//! count, and a per-message wall-clock timeout reset on each fragment.
+
+use super::gid_oid_of;
+use log::warn;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Maximum total reassembled bytes accepted for one message by default.
+pub const DEFAULT_MAX_TOTAL_BYTES: usize = 64 * 1024;
+/// Maximum number of fragments accepted for one message by default.
+pub const DEFAULT_MAX_FRAGMENTS: usize = 256;
+/// Default per-message wall-clock timeout, reset on each received fragment.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Tunable limits for a [`FragmentReassembler`]. `UwbAdaptationImpl` always
+/// builds its reassemblers with [`ReassemblyLimits::default`]; it has no
+/// option for setting other limits.
+#[derive(Debug, Clone, Copy)]
+pub struct ReassemblyLimits {
+    pub max_total_bytes: usize,
+    pub max_fragments: usize,
+    pub timeout: Duration,
+}
+
+impl Default for ReassemblyLimits {
+    fn default() -> Self {
+        ReassemblyLimits {
+            max_total_bytes: DEFAULT_MAX_TOTAL_BYTES,
+            max_fragments: DEFAULT_MAX_FRAGMENTS,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+struct StreamState {
+    total_bytes: usize,
+    fragment_count: usize,
+    last_fragment_at: Instant,
+    /// Set once a limit is exceeded; further fragments for this key are
+    /// dropped until a final (non-continuation) fragment closes it out.
+    blocked: bool,
+}
+
+impl StreamState {
+    fn new(now: Instant) -> Self {
+        StreamState { total_bytes: 0, fragment_count: 0, last_fragment_at: now, blocked: false }
+    }
+}
+
+/// Returns whether the packet-boundary-flag bit is set (more fragments
+/// follow) in a raw UCI fragment's first header byte.
+fn more_fragments_follow(data: &[u8]) -> bool {
+    data.first().map(|b| b & 0x10 != 0).unwrap_or(false)
+}
+
+/// Gates raw UCI fragments by GID/OID key before they reach the real
+/// defragmenter.
+pub struct FragmentReassembler {
+    limits: ReassemblyLimits,
+    streams: HashMap<(u8, u8), StreamState>,
+}
+
+impl FragmentReassembler {
+    pub fn new(limits: ReassemblyLimits) -> Self {
+        FragmentReassembler { limits, streams: HashMap::new() }
+    }
+
+    /// Returns `true` if `data` (one raw UCI fragment) should be forwarded
+    /// to the real defragmenter; `false` if it was dropped because its
+    /// message exceeded a limit, or because it is still blocked from an
+    /// earlier drop.
+    pub fn admit(&mut self, data: &[u8]) -> bool {
+        let key = match gid_oid_of(data) {
+            Some(key) => key,
+            // Too short to key at all; let the real defragmenter reject it.
+            None => return true,
+        };
+        let more_fragments = more_fragments_follow(data);
+        let now = Instant::now();
+
+        let state = self.streams.entry(key).or_insert_with(|| StreamState::new(now));
+
+        if state.blocked {
+            if !more_fragments {
+                // The over-limit message has now closed out; the next
+                // fragment for this key starts a fresh message.
+                self.streams.remove(&key);
+            }
+            return false;
+        }
+
+        if now.duration_since(state.last_fragment_at) > self.limits.timeout {
+            *state = StreamState::new(now);
+        }
+        state.last_fragment_at = now;
+        state.total_bytes += data.len();
+        state.fragment_count += 1;
+
+        if state.total_bytes > self.limits.max_total_bytes || state.fragment_count > self.limits.max_fragments
+        {
+            warn!(
+                "Dropping UCI reassembly for gid/oid {:?}: {} bytes over {} fragments exceeded limits",
+                key, state.total_bytes, state.fragment_count
+            );
+            if more_fragments {
+                state.blocked = true;
+            } else {
+                self.streams.remove(&key);
+            }
+            return false;
+        }
+
+        if !more_fragments {
+            self.streams.remove(&key);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fragment(gid: u8, oid: u8, more_fragments: bool, len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len.max(2)];
+        data[0] = (gid & 0x0f) | if more_fragments { 0x10 } else { 0x00 };
+        data[1] = oid & 0x3f;
+        data
+    }
+
+    #[test]
+    fn admits_fragments_within_limits() {
+        let mut reassembler = FragmentReassembler::new(ReassemblyLimits {
+            max_total_bytes: 1024,
+            max_fragments: 10,
+            timeout: Duration::from_secs(1),
+        });
+        for _ in 0..5 {
+            assert!(reassembler.admit(&fragment(1, 2, true, 16)));
+        }
+        assert!(reassembler.admit(&fragment(1, 2, false, 16)));
+    }
+
+    #[test]
+    fn drops_after_too_many_fragments() {
+        let mut reassembler = FragmentReassembler::new(ReassemblyLimits {
+            max_total_bytes: 1024 * 1024,
+            max_fragments: 16,
+            timeout: Duration::from_secs(5),
+        });
+        let mut admitted = 0;
+        for _ in 0..120 {
+            if reassembler.admit(&fragment(1, 2, true, 8)) {
+                admitted += 1;
+            }
+        }
+        assert_eq!(admitted, 16);
+        // Still blocked: later continuation fragments keep getting dropped.
+        assert!(!reassembler.admit(&fragment(1, 2, true, 8)));
+        // A final fragment closes the blocked message out.
+        assert!(!reassembler.admit(&fragment(1, 2, false, 8)));
+        // The next message for the same key starts fresh.
+        assert!(reassembler.admit(&fragment(1, 2, false, 8)));
+    }
+
+    #[test]
+    fn drops_oversized_payload() {
+        let mut reassembler = FragmentReassembler::new(ReassemblyLimits {
+            max_total_bytes: 64,
+            max_fragments: 1000,
+            timeout: Duration::from_secs(5),
+        });
+        assert!(!reassembler.admit(&fragment(1, 2, false, 256)));
+    }
+
+    #[test]
+    fn unrelated_keys_do_not_interfere() {
+        let mut reassembler = FragmentReassembler::new(ReassemblyLimits {
+            max_total_bytes: 32,
+            max_fragments: 2,
+            timeout: Duration::from_secs(5),
+        });
+        assert!(reassembler.admit(&fragment(1, 2, true, 8)));
+        assert!(reassembler.admit(&fragment(1, 2, true, 8)));
+        // This key is now blocked, but a different gid/oid is unaffected.
+        assert!(!reassembler.admit(&fragment(1, 2, true, 8)));
+        assert!(reassembler.admit(&fragment(3, 4, false, 8)));
+    }
+}
@@ -1,5 +1,14 @@
 //! HAL interface.
 
+mod credit;
+pub mod disassemble;
+mod event_log;
+mod fragment;
+mod pcapng;
+mod recovery;
+mod retryer;
+mod tap;
+
 use crate::error::UwbErr;
 use crate::uci::uci_hrcv;
 use crate::uci::uci_logger::{RealFileFactory, UciLogMode, UciLogger, UciLoggerImpl};
@@ -20,27 +29,142 @@ use binder_tokio::{Tokio, TokioRuntime};
 use log::error;
 #[cfg(target_os = "android")]
 use rustutils::system_properties;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::runtime::Handle;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{mpsc, oneshot, Mutex};
 use uwb_uci_packets::{
-    Packet, PacketDefrager, UciCommandPacket, UciPacketChild, UciPacketHalPacket, UciPacketPacket,
+    Packet, PacketDefrager, UciCommandPacket, UciDataPacket, UciPacketChild, UciPacketHalPacket,
+    UciPacketPacket,
 };
 
+pub use retryer::Retryer;
+
 type Result<T> = std::result::Result<T, UwbErr>;
 type SyncUciLogger = Arc<dyn UciLogger + Send + Sync>;
+type SyncPcapngCapture = Arc<Mutex<pcapng::PcapngWriter>>;
+type SyncUciTap = Arc<Mutex<tap::UciTap>>;
+type SyncEventLogger = Arc<event_log::NotificationEventLogger>;
+type SyncCreditTracker = Arc<credit::CreditTracker>;
+/// Group id + opcode id of an in-flight command, used to match the
+/// response that completes it.
+type GidOid = (u8, u8);
+/// Distinguishes same-(GID, OID) commands in flight at the same time (e.g.
+/// identical commands issued for two different sessions), so a response
+/// resolves the right waiter instead of whichever one happens to still be
+/// in the map.
+type PendingResponseSeq = u64;
+/// Waiters for a response to a specific (GID, OID), so `send_uci_message`
+/// can time out and retry if the chip never answers. Queued per key (oldest
+/// first) rather than a single slot, since two concurrent commands sharing
+/// a GID/OID must not clobber each other's sender.
+type PendingResponses =
+    Arc<Mutex<HashMap<GidOid, VecDeque<(PendingResponseSeq, oneshot::Sender<()>)>>>>;
+
+/// Source of the sequence numbers that disambiguate queued pending
+/// responses sharing a GID/OID.
+static NEXT_PENDING_RESPONSE_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Reads the UCI packet header's group id and opcode id directly from the
+/// raw (defragmented) bytes, common to both commands and responses.
+fn gid_oid_of(data: &[u8]) -> Option<GidOid> {
+    if data.len() < 2 {
+        return None;
+    }
+    Some((data[0] & 0x0f, data[1] & 0x3f))
+}
+
+/// Wakes the oldest waiter registered for `key`, if any. The chip processes
+/// commands sharing a GID/OID in the order they were issued, so the oldest
+/// queued waiter is always the one the response belongs to.
+async fn resolve_pending_response(pending_responses: &PendingResponses, key: &GidOid) {
+    let mut pending = pending_responses.lock().await;
+    if let Some(queue) = pending.get_mut(key) {
+        if let Some((_, waiter)) = queue.pop_front() {
+            let _ = waiter.send(());
+        }
+        if queue.is_empty() {
+            pending.remove(key);
+        }
+    }
+}
+
+/// Removes exactly the waiter registered under `key` with sequence number
+/// `seq` (e.g. after it timed out or its channel closed), leaving any other
+/// in-flight command still waiting on the same (GID, OID) untouched.
+async fn discard_pending_response(pending_responses: &PendingResponses, key: &GidOid, seq: PendingResponseSeq) {
+    let mut pending = pending_responses.lock().await;
+    if let Some(queue) = pending.get_mut(key) {
+        queue.retain(|(entry_seq, _)| *entry_seq != seq);
+        if queue.is_empty() {
+            pending.remove(key);
+        }
+    }
+}
 
 const UCI_LOG_DEFAULT: UciLogMode = UciLogMode::Disabled;
 
+/// Path of the pcapng capture file to write raw UCI traffic to, read from
+/// `UWB_PCAPNG_CAPTURE_PATH`. Unset by default; set it to enable capture
+/// without needing a rebuild.
+fn get_pcapng_capture_path() -> Option<std::path::PathBuf> {
+    std::env::var_os("UWB_PCAPNG_CAPTURE_PATH").map(std::path::PathBuf::from)
+}
+
+/// Path of the raw fragment tap file to write every inbound `onUciMessage`
+/// buffer and outbound fragment to, read from `UWB_UCI_TAP_PATH`. Unset by
+/// default; set it to grow a fixture corpus from a real device without
+/// needing a rebuild. See [`tap`] for the file format and a replay harness.
+fn get_uci_tap_path() -> Option<std::path::PathBuf> {
+    std::env::var_os("UWB_UCI_TAP_PATH").map(std::path::PathBuf::from)
+}
+
+/// Path of the line-delimited JSON ranging event log to write, read from
+/// `UWB_UCI_EVENT_LOG_PATH`. Unset by default. See [`event_log`] for the
+/// schema and for `RingEventSink`, a pluggable in-memory alternative sink.
+fn get_event_log_path() -> Option<std::path::PathBuf> {
+    std::env::var_os("UWB_UCI_EVENT_LOG_PATH").map(std::path::PathBuf::from)
+}
+
 pub struct UwbClientCallback {
+    chip_id: String,
     rsp_sender: mpsc::UnboundedSender<HalCallback>,
     logger: SyncUciLogger,
+    capture: Option<SyncPcapngCapture>,
+    tap: Option<SyncUciTap>,
+    event_logger: Option<SyncEventLogger>,
+    pending_responses: PendingResponses,
+    credits: SyncCreditTracker,
+    reassembler: Mutex<fragment::FragmentReassembler>,
     defrager: Mutex<PacketDefrager>,
 }
 
 impl UwbClientCallback {
-    fn new(rsp_sender: mpsc::UnboundedSender<HalCallback>, logger: SyncUciLogger) -> Self {
-        UwbClientCallback { rsp_sender, logger, defrager: Default::default() }
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        chip_id: String,
+        rsp_sender: mpsc::UnboundedSender<HalCallback>,
+        logger: SyncUciLogger,
+        capture: Option<SyncPcapngCapture>,
+        tap: Option<SyncUciTap>,
+        event_logger: Option<SyncEventLogger>,
+        pending_responses: PendingResponses,
+        credits: SyncCreditTracker,
+        reassembly_limits: fragment::ReassemblyLimits,
+    ) -> Self {
+        UwbClientCallback {
+            chip_id,
+            rsp_sender,
+            logger,
+            capture,
+            tap,
+            event_logger,
+            pending_responses,
+            credits,
+            reassembler: Mutex::new(fragment::FragmentReassembler::new(reassembly_limits)),
+            defrager: Default::default(),
+        }
     }
 
     async fn log_uci_packet(&self, packet: UciPacketPacket) {
@@ -50,6 +174,22 @@ impl UwbClientCallback {
             _ => {}
         }
     }
+
+    async fn capture_packet(&self, direction: pcapng::Direction, data: &[u8]) {
+        if let Some(capture) = &self.capture {
+            if let Err(e) = capture.lock().await.write_packet(direction, data) {
+                error!("Failed to write UCI pcapng record: {:?}", e);
+            }
+        }
+    }
+
+    async fn record_tap(&self, direction: tap::TapDirection, data: &[u8]) {
+        if let Some(tap) = &self.tap {
+            if let Err(e) = tap.lock().await.record(direction, data) {
+                error!("Failed to write UCI tap record: {:?}", e);
+            }
+        }
+    }
 }
 
 impl Interface for UwbClientCallback {}
@@ -58,40 +198,72 @@ impl Interface for UwbClientCallback {}
 impl IUwbClientCallbackAsyncServer for UwbClientCallback {
     async fn onHalEvent(&self, event: UwbEvent, event_status: UwbStatus) -> BinderResult<()> {
         self.rsp_sender
-            .send(HalCallback::Event { event, event_status })
+            .send(HalCallback::Event { chip_id: self.chip_id.clone(), event, event_status })
             .unwrap_or_else(|e| error!("Error sending evt callback: {:?}", e));
         Ok(())
     }
 
     async fn onUciMessage(&self, data: &[u8]) -> BinderResult<()> {
+        self.record_tap(tap::TapDirection::ChipToHost, data).await;
+        if !self.reassembler.lock().await.admit(data) {
+            return Ok(());
+        }
         if let Some(packet) = self.defrager.lock().await.defragment_packet(data) {
             // all fragments for the packet received.
             self.log_uci_packet(packet.clone()).await;
+            let raw = packet.clone().to_vec();
+            self.capture_packet(pcapng::Direction::ChipToHost, &raw).await;
             let packet_msg = uci_hrcv::uci_message(packet);
             match packet_msg {
-                Ok(uci_hrcv::UciMessage::Response(evt)) => self
-                    .rsp_sender
-                    .send(HalCallback::UciRsp(evt))
-                    .unwrap_or_else(|e| error!("Error sending uci response: {:?}", e)),
-                Ok(uci_hrcv::UciMessage::Notification(evt)) => self
+                Ok(uci_hrcv::UciMessage::Response(evt)) => {
+                    if let Some(key) = gid_oid_of(&raw) {
+                        resolve_pending_response(&self.pending_responses, &key).await;
+                    }
+                    self.rsp_sender
+                        .send(HalCallback::UciRsp { chip_id: self.chip_id.clone(), rsp: evt })
+                        .unwrap_or_else(|e| error!("Error sending uci response: {:?}", e))
+                }
+                Ok(uci_hrcv::UciMessage::Notification(evt)) => {
+                    if let Some((session_id, credits)) = credit::session_data_credit_ntf(&raw) {
+                        self.credits.grant(session_id, credits).await;
+                    }
+                    if let Some(event_logger) = &self.event_logger {
+                        event_logger.log(&self.chip_id, &evt);
+                    }
+                    self.rsp_sender
+                        .send(HalCallback::UciNtf { chip_id: self.chip_id.clone(), ntf: evt })
+                        .unwrap_or_else(|e| error!("Error sending uci notification: {:?}", e))
+                }
+                Ok(uci_hrcv::UciMessage::Data(evt)) => self
                     .rsp_sender
-                    .send(HalCallback::UciNtf(evt))
-                    .unwrap_or_else(|e| error!("Error sending uci notification: {:?}", e)),
-                _ => error!("UCI message which is neither a UCI RSP or NTF: {:?}", data),
+                    .send(HalCallback::UciData { chip_id: self.chip_id.clone(), data: evt })
+                    .unwrap_or_else(|e| error!("Error sending uci data: {:?}", e)),
+                _ => error!("UCI message which is neither a UCI RSP, NTF, nor DATA: {:?}", data),
             }
         }
         Ok(())
     }
 }
 
-async fn get_hal_service() -> Result<Strong<dyn IUwbChipAsync<Tokio>>> {
+/// Resolves the HAL binder for a single UWB chip by name, as returned by
+/// `IUwb::getChips()`. Callers that need to talk to every chip exposed by
+/// the HAL should call this once per chip id and keep the results in a map,
+/// see [`UwbAdaptationRegistry`].
+async fn get_hal_service(chip_id: &str) -> Result<Strong<dyn IUwbChipAsync<Tokio>>> {
     let service_name: &str = "android.hardware.uwb.IUwb/default";
     let i_uwb: Strong<dyn IUwbAsync<Tokio>> = binder_tokio::get_interface(service_name).await?;
-    let chip_names = i_uwb.getChips().await?;
-    let i_uwb_chip = i_uwb.getChip(&chip_names[0]).await?.into_async();
+    let i_uwb_chip = i_uwb.getChip(chip_id).await?.into_async();
     Ok(i_uwb_chip)
 }
 
+/// Lists every chip name exposed by the UWB HAL, in the order reported by
+/// `IUwb::getChips()`.
+async fn get_chip_names() -> Result<Vec<String>> {
+    let service_name: &str = "android.hardware.uwb.IUwb/default";
+    let i_uwb: Strong<dyn IUwbAsync<Tokio>> = binder_tokio::get_interface(service_name).await?;
+    Ok(i_uwb.getChips().await?)
+}
+
 #[async_trait]
 pub trait UwbAdaptation {
     async fn finalize(&mut self, exit_status: bool);
@@ -100,16 +272,25 @@ pub trait UwbAdaptation {
     async fn core_initialization(&self) -> Result<()>;
     async fn session_initialization(&self, session_id: i32) -> Result<()>;
     async fn send_uci_message(&self, cmd: UciCommandPacket) -> Result<()>;
+    async fn send_data_message(&self, data: UciDataPacket) -> Result<()>;
 }
 
 #[derive(Clone)]
 pub struct UwbAdaptationImpl {
+    chip_id: String,
     hal: Strong<dyn IUwbChipAsync<Tokio>>,
     #[allow(dead_code)]
     // Need to store the death recipient since link_to_death stores a weak pointer.
     hal_death_recipient: Arc<Mutex<DeathRecipient>>,
+    hal_alive: Arc<std::sync::atomic::AtomicBool>,
     rsp_sender: mpsc::UnboundedSender<HalCallback>,
     logger: SyncUciLogger,
+    capture: Option<SyncPcapngCapture>,
+    tap: Option<SyncUciTap>,
+    event_logger: Option<SyncEventLogger>,
+    pending_responses: PendingResponses,
+    credits: SyncCreditTracker,
+    reassembly_limits: fragment::ReassemblyLimits,
 }
 
 impl UwbAdaptationImpl {
@@ -140,26 +321,61 @@ impl UwbAdaptationImpl {
     }
 
     async fn new_with_args(
+        chip_id: String,
         rsp_sender: mpsc::UnboundedSender<HalCallback>,
         hal: Strong<dyn IUwbChipAsync<Tokio>>,
         hal_death_recipient: Arc<Mutex<DeathRecipient>>,
+        hal_alive: Arc<std::sync::atomic::AtomicBool>,
     ) -> Result<Self> {
         let logger = UciLoggerImpl::new(
             UwbAdaptationImpl::get_uci_log_mode(),
             Arc::new(Mutex::new(RealFileFactory::default())),
         )
         .await;
-        Ok(UwbAdaptationImpl { hal, rsp_sender, logger: Arc::new(logger), hal_death_recipient })
+        let capture = match get_pcapng_capture_path() {
+            Some(path) => Some(Arc::new(Mutex::new(pcapng::PcapngWriter::open(&path)?))),
+            None => None,
+        };
+        let tap = match get_uci_tap_path() {
+            Some(path) => Some(Arc::new(Mutex::new(tap::UciTap::open(&path)?))),
+            None => None,
+        };
+        let event_logger = match get_event_log_path() {
+            Some(path) => {
+                let sink = Arc::new(event_log::FileEventSink::open(&path)?);
+                Some(Arc::new(event_log::NotificationEventLogger::new(sink)))
+            }
+            None => None,
+        };
+        Ok(UwbAdaptationImpl {
+            chip_id,
+            hal,
+            rsp_sender,
+            logger: Arc::new(logger),
+            capture,
+            tap,
+            event_logger,
+            hal_death_recipient,
+            hal_alive,
+            pending_responses: Arc::new(Mutex::new(HashMap::new())),
+            credits: Arc::new(credit::CreditTracker::new()),
+            reassembly_limits: fragment::ReassemblyLimits::default(),
+        })
     }
 
-    pub async fn new(rsp_sender: mpsc::UnboundedSender<HalCallback>) -> Result<Self> {
-        let hal = get_hal_service().await?;
+    pub async fn new(chip_id: String, rsp_sender: mpsc::UnboundedSender<HalCallback>) -> Result<Self> {
+        let hal = get_hal_service(&chip_id).await?;
         let rsp_sender_clone = rsp_sender.clone();
+        let chip_id_clone = chip_id.clone();
+        let hal_alive = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let hal_alive_clone = hal_alive.clone();
         let mut hal_death_recipient = DeathRecipient::new(move || {
-            error!("UWB HAL died. Resetting stack...");
+            error!("UWB HAL died for chip {}. Resetting stack...", chip_id_clone);
+            hal_alive_clone.store(false, std::sync::atomic::Ordering::SeqCst);
             // Send error HAL event to trigger stack recovery.
             rsp_sender_clone
                 .send(HalCallback::Event {
+                    chip_id: chip_id_clone.clone(),
                     event: UwbEvent::ERROR,
                     event_status: UwbStatus::FAILED,
                 })
@@ -167,7 +383,14 @@ impl UwbAdaptationImpl {
         });
         // Register for death notification.
         hal.as_binder().link_to_death(&mut hal_death_recipient)?;
-        Self::new_with_args(rsp_sender, hal, Arc::new(Mutex::new(hal_death_recipient))).await
+        Self::new_with_args(
+            chip_id,
+            rsp_sender,
+            hal,
+            Arc::new(Mutex::new(hal_death_recipient)),
+            hal_alive,
+        )
+        .await
     }
 }
 
@@ -177,7 +400,17 @@ impl UwbAdaptation for UwbAdaptationImpl {
 
     async fn hal_open(&self) -> Result<()> {
         let m_cback = BnUwbClientCallback::new_async_binder(
-            UwbClientCallback::new(self.rsp_sender.clone(), self.logger.clone()),
+            UwbClientCallback::new(
+                self.chip_id.clone(),
+                self.rsp_sender.clone(),
+                self.logger.clone(),
+                self.capture.clone(),
+                self.tap.clone(),
+                self.event_logger.clone(),
+                self.pending_responses.clone(),
+                self.credits.clone(),
+                self.reassembly_limits,
+            ),
             TokioRuntime(Handle::current()),
             BinderFeatures::default(),
         );
@@ -200,12 +433,190 @@ impl UwbAdaptation for UwbAdaptationImpl {
     async fn send_uci_message(&self, cmd: UciCommandPacket) -> Result<()> {
         self.logger.log_uci_command(cmd.clone()).await;
         let packet: UciPacketPacket = cmd.into();
+        let raw = packet.clone().to_vec();
+        if let Some(capture) = &self.capture {
+            if let Err(e) = capture.lock().await.write_packet(pcapng::Direction::HostToChip, &raw)
+            {
+                error!("Failed to write UCI pcapng record: {:?}", e);
+            }
+        }
         // fragment packet.
         let fragmented_packets: Vec<UciPacketHalPacket> = packet.into();
-        for packet in fragmented_packets {
-            self.hal.sendUciMessage(&packet.to_vec()).await?;
+        let key = gid_oid_of(&raw);
+        let retryer = Retryer::default();
+        for attempt in 0..retryer.max_attempts() {
+            if !self.hal_alive.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(UwbErr::Specialized("HAL died while sending UCI command".to_string()));
+            }
+            let receiver = match key {
+                Some(key) => {
+                    let (tx, rx) = oneshot::channel();
+                    let seq = NEXT_PENDING_RESPONSE_SEQ.fetch_add(1, Ordering::Relaxed);
+                    self.pending_responses
+                        .lock()
+                        .await
+                        .entry(key)
+                        .or_default()
+                        .push_back((seq, tx));
+                    Some((seq, rx))
+                }
+                None => None,
+            };
+            for packet in &fragmented_packets {
+                let frag_data = packet.to_vec();
+                if let Some(tap) = &self.tap {
+                    if let Err(e) = tap.lock().await.record(tap::TapDirection::HostToChip, &frag_data) {
+                        error!("Failed to write UCI tap record: {:?}", e);
+                    }
+                }
+                self.hal.sendUciMessage(&frag_data).await?;
+            }
+            // TODO should we be validating the returned number?
+            let (seq, receiver) = match receiver {
+                Some(receiver) => receiver,
+                // Command has no GID/OID to correlate a response to (shouldn't
+                // happen for well-formed UCI commands); fall back to fire-and-forget.
+                None => return Ok(()),
+            };
+            match tokio::time::timeout(retryer.timeout(), receiver).await {
+                Ok(Ok(())) => return Ok(()),
+                Ok(Err(_)) => {
+                    // The sender was dropped without ever being resolved; no
+                    // real response arrived.
+                    if let Some(key) = key {
+                        discard_pending_response(&self.pending_responses, &key, seq).await;
+                    }
+                    if !retryer.has_more_retries(attempt) {
+                        error!("UCI command response channel closed after {} attempts", attempt + 1);
+                        return Err(UwbErr::Specialized(
+                            "UCI command response channel closed before a response arrived"
+                                .to_string(),
+                        ));
+                    }
+                    error!(
+                        "UCI command response channel closed before a response arrived, retrying (attempt {})",
+                        attempt + 1
+                    );
+                }
+                Err(_) => {
+                    if let Some(key) = key {
+                        discard_pending_response(&self.pending_responses, &key, seq).await;
+                    }
+                    if !retryer.has_more_retries(attempt) {
+                        error!("UCI command timed out after {} attempts", attempt + 1);
+                        return Err(UwbErr::Specialized("UCI command timed out".to_string()));
+                    }
+                    error!("UCI command timed out, retrying (attempt {})", attempt + 1);
+                }
+            }
+        }
+        Err(UwbErr::Specialized("UCI command timed out".to_string()))
+    }
+
+    async fn send_data_message(&self, data: UciDataPacket) -> Result<()> {
+        let session_id = data.get_session_id();
+        // Block until the controller has advertised room for this session's
+        // next data message instead of risking an overrun.
+        self.credits.acquire(session_id).await;
+        let packet: UciPacketPacket = data.into();
+        let fragmented_packets: Vec<UciPacketHalPacket> = packet.into();
+        for packet in &fragmented_packets {
+            let frag_data = packet.to_vec();
+            if let Some(tap) = &self.tap {
+                if let Err(e) = tap.lock().await.record(tap::TapDirection::HostToChip, &frag_data) {
+                    error!("Failed to write UCI tap record: {:?}", e);
+                }
+            }
+            self.hal.sendUciMessage(&frag_data).await?;
         }
-        // TODO should we be validating the returned number?
+        Ok(())
+    }
+}
+
+/// Owns one [`UwbAdaptationImpl`] per UWB chip exposed by the HAL, keyed by
+/// chip name, so the stack can address every chip on a multi-radio device
+/// instead of only `chip_names[0]`.
+pub struct UwbAdaptationRegistry {
+    rsp_sender: mpsc::UnboundedSender<HalCallback>,
+    adaptations: Arc<Mutex<HashMap<String, UwbAdaptationImpl>>>,
+    /// Session ids successfully initialized per chip, so a crashed chip can
+    /// have them re-initialized as part of recovery.
+    active_sessions: Arc<Mutex<HashMap<String, std::collections::HashSet<i32>>>>,
+    /// Death recipients registered solely to drive recovery; kept alive here
+    /// since `link_to_death` only holds a weak reference. Shared with
+    /// [`recovery::recover_chip`], which re-arms a fresh one after each
+    /// successful recovery.
+    recovery_death_recipients: Arc<Mutex<HashMap<String, Arc<Mutex<DeathRecipient>>>>>,
+}
+
+impl UwbAdaptationRegistry {
+    /// Discovers every chip reported by `IUwb::getChips()` and creates an
+    /// adaptation for each of them up front.
+    pub async fn new(rsp_sender: mpsc::UnboundedSender<HalCallback>) -> Result<Self> {
+        let registry = UwbAdaptationRegistry {
+            rsp_sender,
+            adaptations: Arc::new(Mutex::new(HashMap::new())),
+            active_sessions: Arc::new(Mutex::new(HashMap::new())),
+            recovery_death_recipients: Arc::new(Mutex::new(HashMap::new())),
+        };
+        for chip_id in get_chip_names().await? {
+            registry.add_chip(chip_id).await?;
+        }
+        Ok(registry)
+    }
+
+    async fn add_chip(&self, chip_id: String) -> Result<()> {
+        let adaptation = UwbAdaptationImpl::new(chip_id.clone(), self.rsp_sender.clone()).await?;
+        self.adaptations.lock().await.insert(chip_id.clone(), adaptation);
+        self.watch_for_recovery(chip_id).await?;
+        Ok(())
+    }
+
+    /// Registers a second, registry-owned death recipient for `chip_id`
+    /// purely to drive [`recovery::recover_chip`], independent of the
+    /// `ERROR` event that `UwbAdaptationImpl`'s own recipient fires on the
+    /// same underlying death. `recover_chip` re-arms this itself after each
+    /// successful recovery, so later crashes on the same chip are caught too.
+    async fn watch_for_recovery(&self, chip_id: String) -> Result<()> {
+        recovery::watch_for_recovery(
+            chip_id,
+            self.adaptations.clone(),
+            self.active_sessions.clone(),
+            self.rsp_sender.clone(),
+            self.recovery_death_recipients.clone(),
+        )
+        .await
+    }
+
+    async fn get(&self, chip_id: &str) -> Result<UwbAdaptationImpl> {
+        self.adaptations
+            .lock()
+            .await
+            .get(chip_id)
+            .cloned()
+            .ok_or_else(|| UwbErr::Specialized(format!("unknown chip_id: {}", chip_id)))
+    }
+
+    pub async fn send_uci_message(&self, chip_id: &str, cmd: UciCommandPacket) -> Result<()> {
+        self.get(chip_id).await?.send_uci_message(cmd).await
+    }
+
+    pub async fn send_data_message(&self, chip_id: &str, data: UciDataPacket) -> Result<()> {
+        self.get(chip_id).await?.send_data_message(data).await
+    }
+
+    pub async fn core_initialization(&self, chip_id: &str) -> Result<()> {
+        self.get(chip_id).await?.core_initialization().await
+    }
+
+    pub async fn session_initialization(&self, chip_id: &str, session_id: i32) -> Result<()> {
+        self.get(chip_id).await?.session_initialization(session_id).await?;
+        self.active_sessions
+            .lock()
+            .await
+            .entry(chip_id.to_string())
+            .or_default()
+            .insert(session_id);
         Ok(())
     }
 }
@@ -227,7 +638,17 @@ pub mod tests {
         rsp_sender: mpsc::UnboundedSender<HalCallback>,
     ) -> UwbClientCallback {
         // Add tests for the mock logger.
-        UwbClientCallback::new(rsp_sender, Arc::new(MockUciLogger::new()))
+        UwbClientCallback::new(
+            "mock_chip".to_string(),
+            rsp_sender,
+            Arc::new(MockUciLogger::new()),
+            None,
+            None,
+            None,
+            Arc::new(Mutex::new(HashMap::new())),
+            Arc::new(credit::CreditTracker::new()),
+            fragment::ReassemblyLimits::default(),
+        )
     }
 
     fn setup_client_callback() -> (mpsc::UnboundedReceiver<HalCallback>, UwbClientCallback) {
@@ -250,7 +671,10 @@ pub mod tests {
         let result = uwb_client_callback.onHalEvent(event, event_status).await;
         assert_eq!(result, Ok(()));
         let response = rsp_receiver.recv().await;
-        assert!(matches!(response, Some(HalCallback::Event { event: _, event_status: _ })));
+        assert!(matches!(
+            response,
+            Some(HalCallback::Event { chip_id: _, event: _, event_status: _ })
+        ));
     }
 
     #[tokio::test]
@@ -265,7 +689,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::GetDeviceInfoRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::GetDeviceInfoRsp(_), .. })
         ));
     }
 
@@ -278,7 +702,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::GetCapsInfoRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::GetCapsInfoRsp(_), .. })
         ));
     }
 
@@ -291,7 +715,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::SetConfigRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::SetConfigRsp(_), .. })
         ));
     }
 
@@ -304,7 +728,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::GetConfigRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::GetConfigRsp(_), .. })
         ));
     }
 
@@ -317,7 +741,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::DeviceResetRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::DeviceResetRsp(_), .. })
         ));
     }
 
@@ -330,7 +754,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::SessionInitRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::SessionInitRsp(_), .. })
         ));
     }
 
@@ -343,7 +767,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::SessionDeinitRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::SessionDeinitRsp(_), .. })
         ));
     }
 
@@ -356,7 +780,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::SessionGetAppConfigRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::SessionGetAppConfigRsp(_), .. })
         ));
     }
 
@@ -369,7 +793,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::SessionSetAppConfigRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::SessionSetAppConfigRsp(_), .. })
         ));
     }
 
@@ -382,7 +806,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::SessionGetStateRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::SessionGetStateRsp(_), .. })
         ));
     }
 
@@ -395,7 +819,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::SessionGetCountRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::SessionGetCountRsp(_), .. })
         ));
     }
 
@@ -408,9 +832,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(
-                uci_hrcv::UciResponse::SessionUpdateControllerMulticastListRsp(_)
-            ))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::SessionUpdateControllerMulticastListRsp(_), .. })
         ));
     }
 
@@ -423,7 +845,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::RangeStartRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::RangeStartRsp(_), .. })
         ));
     }
 
@@ -436,7 +858,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::RangeStopRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::RangeStopRsp(_), .. })
         ));
     }
 
@@ -449,7 +871,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::AndroidSetCountryCodeRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::AndroidSetCountryCodeRsp(_), .. })
         ));
     }
 
@@ -465,7 +887,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::AndroidGetPowerStatsRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::AndroidGetPowerStatsRsp(_), .. })
         ));
     }
 
@@ -514,7 +936,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciRsp(uci_hrcv::UciResponse::RawVendorRsp(_)))
+            Some(HalCallback::UciRsp { rsp: uci_hrcv::UciResponse::RawVendorRsp(_), .. })
         ));
     }
 
@@ -527,7 +949,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciNtf(uci_hrcv::UciNotification::GenericError(_)))
+            Some(HalCallback::UciNtf { ntf: uci_hrcv::UciNotification::GenericError(_), .. })
         ));
     }
 
@@ -540,7 +962,28 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciNtf(uci_hrcv::UciNotification::DeviceStatusNtf(_)))
+            Some(HalCallback::UciNtf { ntf: uci_hrcv::UciNotification::DeviceStatusNtf(_), .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_replay_chip_to_host() {
+        let path = std::env::temp_dir()
+            .join(format!("uci_tap_replay_test_{:?}", std::thread::current().id()));
+        let mut recorded_tap = tap::UciTap::open(&path).unwrap();
+        recorded_tap
+            .record(tap::TapDirection::ChipToHost, &[0x60, 0x01, 0x00, 0x01, 0x01])
+            .unwrap();
+        drop(recorded_tap);
+
+        let (mut rsp_receiver, uwb_client_callback) = setup_client_callback();
+        tap::replay_chip_to_host(&path, &uwb_client_callback).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let response = rsp_receiver.recv().await;
+        assert!(matches!(
+            response,
+            Some(HalCallback::UciNtf { ntf: uci_hrcv::UciNotification::DeviceStatusNtf(_), .. })
         ));
     }
 
@@ -553,7 +996,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciNtf(uci_hrcv::UciNotification::SessionStatusNtf(_)))
+            Some(HalCallback::UciNtf { ntf: uci_hrcv::UciNotification::SessionStatusNtf(_), .. })
         ));
     }
 
@@ -566,9 +1009,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciNtf(
-                uci_hrcv::UciNotification::SessionUpdateControllerMulticastListNtf(_)
-            ))
+            Some(HalCallback::UciNtf { ntf: uci_hrcv::UciNotification::SessionUpdateControllerMulticastListNtf(_), .. })
         ));
     }
 
@@ -585,7 +1026,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciNtf(uci_hrcv::UciNotification::ShortMacTwoWayRangeDataNtf(_)))
+            Some(HalCallback::UciNtf { ntf: uci_hrcv::UciNotification::ShortMacTwoWayRangeDataNtf(_), .. })
         ));
     }
 
@@ -602,7 +1043,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciNtf(uci_hrcv::UciNotification::ExtendedMacTwoWayRangeDataNtf(_)))
+            Some(HalCallback::UciNtf { ntf: uci_hrcv::UciNotification::ExtendedMacTwoWayRangeDataNtf(_), .. })
         ));
     }
 
@@ -651,7 +1092,7 @@ pub mod tests {
         let response = rsp_receiver.recv().await;
         assert!(matches!(
             response,
-            Some(HalCallback::UciNtf(uci_hrcv::UciNotification::RawVendorNtf(_)))
+            Some(HalCallback::UciNtf { ntf: uci_hrcv::UciNotification::RawVendorNtf(_), .. })
         ));
     }
 
@@ -668,6 +1109,24 @@ pub mod tests {
         assert!(response.is_err());
     }
 
+    /// Resolves the pending-response waiter for `raw` (an unfragmented UCI
+    /// packet) the moment `send_uci_message` registers it, so tests that
+    /// don't drive a real HAL response path don't have to sit out the
+    /// retry timeout.
+    fn spawn_ack_pending_response(adaptation_impl: &UwbAdaptationImpl, raw: Vec<u8>) {
+        let key = gid_oid_of(&raw).expect("test command too short to key a response");
+        let pending_responses = adaptation_impl.pending_responses.clone();
+        tokio::spawn(async move {
+            loop {
+                if pending_responses.lock().await.contains_key(&key) {
+                    resolve_pending_response(&pending_responses, &key).await;
+                    break;
+                }
+                tokio::task::yield_now().await;
+            }
+        });
+    }
+
     async fn setup_adaptation_impl(config_fn: impl Fn(&MockHal)) -> Result<UwbAdaptationImpl> {
         // TODO: Remove this once we call it somewhere real.
         logger::init(
@@ -680,9 +1139,11 @@ pub mod tests {
         config_fn(&mock_hal);
 
         UwbAdaptationImpl::new_with_args(
+            "mock_chip".to_string(),
             rsp_sender,
             binder::Strong::new(Box::new(mock_hal)),
             Arc::new(Mutex::new(DeathRecipient::new(|| {}))),
+            Arc::new(std::sync::atomic::AtomicBool::new(true)),
         )
         .await
     }
@@ -700,6 +1161,8 @@ pub mod tests {
         })
         .await
         .unwrap();
+        let cmd_packet: UciPacketPacket = cmd.clone().into();
+        spawn_ack_pending_response(&adaptation_impl, cmd_packet.to_vec());
         adaptation_impl.send_uci_message(cmd).await.unwrap();
     }
 
@@ -793,12 +1256,46 @@ pub mod tests {
             Ok(cmd_frag_data_len_2.try_into().unwrap()),
         );
         let adaptation_impl = UwbAdaptationImpl::new_with_args(
+            "mock_chip".to_string(),
             rsp_sender,
             binder::Strong::new(Box::new(mock_hal)),
             Arc::new(Mutex::new(DeathRecipient::new(|| {}))),
+            Arc::new(std::sync::atomic::AtomicBool::new(true)),
         )
         .await
         .unwrap();
+        let cmd_packet: UciPacketPacket = cmd.clone().into();
+        spawn_ack_pending_response(&adaptation_impl, cmd_packet.to_vec());
         adaptation_impl.send_uci_message(cmd).await.unwrap();
     }
+
+    /// Two concurrent commands sharing a GID/OID (e.g. the same command
+    /// issued for two different sessions) must each be resolved by their own
+    /// response, not have the second registration silently clobber the
+    /// first's sender.
+    #[tokio::test]
+    async fn test_pending_responses_with_shared_gid_oid_are_queued_independently() {
+        let pending_responses: PendingResponses = Arc::new(Mutex::new(HashMap::new()));
+        let key: GidOid = (0x0a, 0x01);
+
+        let (tx1, rx1) = oneshot::channel();
+        let seq1 = NEXT_PENDING_RESPONSE_SEQ.fetch_add(1, Ordering::Relaxed);
+        pending_responses.lock().await.entry(key).or_default().push_back((seq1, tx1));
+
+        let (tx2, rx2) = oneshot::channel();
+        let seq2 = NEXT_PENDING_RESPONSE_SEQ.fetch_add(1, Ordering::Relaxed);
+        pending_responses.lock().await.entry(key).or_default().push_back((seq2, tx2));
+
+        // A single response for `key` must resolve the oldest (first)
+        // registration only, leaving the second's sender intact.
+        resolve_pending_response(&pending_responses, &key).await;
+        assert_eq!(rx1.await, Ok(()));
+        assert!(pending_responses.lock().await.contains_key(&key));
+
+        // Discarding the second by its own sequence number (e.g. after it
+        // times out) must not resolve it as if a response had arrived.
+        discard_pending_response(&pending_responses, &key, seq2).await;
+        assert!(rx2.await.is_err());
+        assert!(!pending_responses.lock().await.contains_key(&key));
+    }
 }
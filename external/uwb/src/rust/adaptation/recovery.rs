@@ -0,0 +1,175 @@
+//! Deterministic teardown-and-restore sequence run after HAL death: drop the
+//! dead binder handle, re-resolve the service, re-link to death, re-open the
+//! HAL and core-init it, then re-initialize whichever sessions were active
+//! before the crash. Backs off exponentially across consecutive failures so
+//! a chip stuck in a crash loop doesn't spin the runtime.
+
+use super::{get_hal_service, uci_hrcv, HalCallback, Result, UwbAdaptation, UwbAdaptationImpl};
+use android_hardware_uwb::binder::{DeathRecipient, Interface};
+use binder::IBinder;
+use log::error;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use uwb_uci_packets::{Packet, PacketDefrager};
+
+type RecoveryDeathRecipients = Arc<Mutex<HashMap<String, Arc<Mutex<DeathRecipient>>>>>;
+
+/// Delay before the first recovery attempt.
+pub const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// Recovery delay is capped here regardless of how many attempts have failed.
+pub const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Consecutive failed recovery attempts allowed before giving up entirely.
+pub const MAX_CONSECUTIVE_ATTEMPTS: u32 = 5;
+
+/// Tracks exponential backoff across repeated recovery attempts for one chip.
+#[derive(Debug, Clone, Copy, Default)]
+struct RecoveryBackoff {
+    attempt: u32,
+}
+
+impl RecoveryBackoff {
+    fn exhausted(&self) -> bool {
+        self.attempt >= MAX_CONSECUTIVE_ATTEMPTS
+    }
+
+    fn delay(&self) -> Duration {
+        let shift = self.attempt.min(16);
+        let millis = (INITIAL_BACKOFF.as_millis() as u64).saturating_mul(1u64 << shift);
+        Duration::from_millis(millis.min(MAX_BACKOFF.as_millis() as u64))
+    }
+
+    fn record_failure(&mut self) {
+        self.attempt = self.attempt.saturating_add(1);
+    }
+}
+
+/// Raw `DEVICE_STATUS_NTF` (GID 0x00 core, OID 0x01) bytes carrying
+/// `device_state`, the same shape a real chip would send, used to build a
+/// synthesized notification without needing a packet builder of our own.
+fn device_status_ntf_bytes(device_state: u8) -> [u8; 5] {
+    [0x60, 0x01, 0x00, 0x01, device_state]
+}
+
+/// FiRa `DEVICE_STATE_ERROR` value.
+const DEVICE_STATE_ERROR: u8 = 0x01;
+
+/// Builds a `DeviceStatusNtf` reporting an error/reset state, so clients
+/// react the same way they would to a chip-reported crash.
+fn synthesize_device_status_error() -> Option<uci_hrcv::UciNotification> {
+    let packet =
+        PacketDefrager::default().defragment_packet(&device_status_ntf_bytes(DEVICE_STATE_ERROR))?;
+    match uci_hrcv::uci_message(packet) {
+        Ok(uci_hrcv::UciMessage::Notification(ntf)) => Some(ntf),
+        _ => None,
+    }
+}
+
+/// Runs the teardown-and-restore sequence for `chip_id`, retrying with
+/// exponential backoff until it succeeds or [`MAX_CONSECUTIVE_ATTEMPTS`] is
+/// reached. On success, installs the freshly-recovered adaptation into
+/// `adaptations`, re-arms [`watch_for_recovery`] against the new binder so a
+/// later crash on the same chip is still caught, and emits a synthesized
+/// `DeviceStatusNtf` upstream.
+pub(super) async fn recover_chip(
+    chip_id: String,
+    adaptations: Arc<Mutex<HashMap<String, UwbAdaptationImpl>>>,
+    active_sessions: Arc<Mutex<HashMap<String, HashSet<i32>>>>,
+    rsp_sender: mpsc::UnboundedSender<HalCallback>,
+    recovery_death_recipients: RecoveryDeathRecipients,
+) {
+    let mut backoff = RecoveryBackoff::default();
+    loop {
+        if backoff.exhausted() {
+            error!(
+                "Giving up recovering UWB chip {} after {} attempts",
+                chip_id, MAX_CONSECUTIVE_ATTEMPTS
+            );
+            return;
+        }
+        tokio::time::sleep(backoff.delay()).await;
+        match try_recover_once(&chip_id, &active_sessions, &rsp_sender).await {
+            Ok(adaptation) => {
+                adaptations.lock().await.insert(chip_id.clone(), adaptation);
+                if let Err(e) = watch_for_recovery(
+                    chip_id.clone(),
+                    adaptations.clone(),
+                    active_sessions.clone(),
+                    rsp_sender.clone(),
+                    recovery_death_recipients.clone(),
+                )
+                .await
+                {
+                    error!("Failed to re-arm recovery watch for UWB chip {}: {:?}", chip_id, e);
+                }
+                if let Some(ntf) = synthesize_device_status_error() {
+                    rsp_sender
+                        .send(HalCallback::UciNtf { chip_id: chip_id.clone(), ntf })
+                        .unwrap_or_else(|e| error!("Error sending recovery status ntf: {:?}", e));
+                }
+                return;
+            }
+            Err(e) => {
+                error!("UWB chip {} recovery attempt failed: {:?}", chip_id, e);
+                backoff.record_failure();
+            }
+        }
+    }
+}
+
+/// Registers a second, registry-owned death recipient for `chip_id` purely
+/// to drive [`recover_chip`], independent of the `ERROR` event that
+/// `UwbAdaptationImpl`'s own recipient fires on the same underlying death.
+/// Called once when a chip is first discovered, and again by `recover_chip`
+/// after each successful recovery, since a `DeathRecipient` only fires once
+/// per binder and the recovered chip gets a freshly-linked one.
+pub(super) async fn watch_for_recovery(
+    chip_id: String,
+    adaptations: Arc<Mutex<HashMap<String, UwbAdaptationImpl>>>,
+    active_sessions: Arc<Mutex<HashMap<String, HashSet<i32>>>>,
+    rsp_sender: mpsc::UnboundedSender<HalCallback>,
+    recovery_death_recipients: RecoveryDeathRecipients,
+) -> Result<()> {
+    let hal = get_hal_service(&chip_id).await?;
+    let recipient_key = chip_id.clone();
+    let outer_recipients = recovery_death_recipients.clone();
+    let mut death_recipient = DeathRecipient::new(move || {
+        let chip_id = chip_id.clone();
+        let adaptations = adaptations.clone();
+        let active_sessions = active_sessions.clone();
+        let rsp_sender = rsp_sender.clone();
+        let recovery_death_recipients = recovery_death_recipients.clone();
+        tokio::spawn(recover_chip(
+            chip_id,
+            adaptations,
+            active_sessions,
+            rsp_sender,
+            recovery_death_recipients,
+        ));
+    });
+    hal.as_binder().link_to_death(&mut death_recipient)?;
+    outer_recipients.lock().await.insert(recipient_key, Arc::new(Mutex::new(death_recipient)));
+    Ok(())
+}
+
+async fn try_recover_once(
+    chip_id: &str,
+    active_sessions: &Arc<Mutex<HashMap<String, HashSet<i32>>>>,
+    rsp_sender: &mpsc::UnboundedSender<HalCallback>,
+) -> Result<UwbAdaptationImpl> {
+    // Checks that the service resolves again before building a new
+    // adaptation (which resolves it once more). The dead adaptation and its
+    // binder handle stay in `adaptations` until `recover_chip` replaces them
+    // after a successful attempt.
+    get_hal_service(chip_id).await?;
+    let adaptation = UwbAdaptationImpl::new(chip_id.to_string(), rsp_sender.clone()).await?;
+    adaptation.hal_open().await?;
+    adaptation.core_initialization().await?;
+    if let Some(sessions) = active_sessions.lock().await.get(chip_id) {
+        for &session_id in sessions {
+            adaptation.session_initialization(session_id).await?;
+        }
+    }
+    Ok(adaptation)
+}
@@ -0,0 +1,47 @@
+//! Command timeout/retry policy for the adaptation send path.
+
+use std::time::Duration;
+
+/// Number of times a command is resent before giving up, not counting the
+/// initial send.
+pub const MAX_RETRIES: u32 = 2;
+
+/// How long to wait for a matching response before resending.
+pub const RESPONSE_TIMEOUT: Duration = Duration::from_millis(2000);
+
+/// Tracks retry/timeout policy for a single in-flight UCI command. Created
+/// fresh per `send_uci_message` call; `UwbAdaptationImpl` drives the actual
+/// send+wait loop since it alone has access to the HAL and the pending
+/// response registry.
+#[derive(Debug, Clone, Copy)]
+pub struct Retryer {
+    max_retries: u32,
+    timeout: Duration,
+}
+
+impl Default for Retryer {
+    fn default() -> Self {
+        Retryer { max_retries: MAX_RETRIES, timeout: RESPONSE_TIMEOUT }
+    }
+}
+
+impl Retryer {
+    pub fn new(max_retries: u32, timeout: Duration) -> Self {
+        Retryer { max_retries, timeout }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Total number of sends (initial attempt + retries).
+    pub fn max_attempts(&self) -> u32 {
+        self.max_retries + 1
+    }
+
+    /// Whether `attempt` (0-indexed) should be followed by another resend
+    /// if it times out.
+    pub fn has_more_retries(&self, attempt: u32) -> bool {
+        attempt + 1 < self.max_attempts()
+    }
+}
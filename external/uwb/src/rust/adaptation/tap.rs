@@ -0,0 +1,149 @@
+//! Record/replay of raw UCI HAL traffic for building a regression corpus
+//! from real device captures, the way Suricata ships small fixture
+//! captures (`dhcp/ack.pcap`, `template.pcap`) alongside each parser.
+//!
+//! Unlike [`crate::adaptation::pcapng`], which captures reassembled
+//! packets for inspection in third-party tools, a [`UciTap`] captures the
+//! exact raw buffers `UwbClientCallback`/`UwbAdaptationImpl` saw or sent,
+//! one record per fragment, so a capture can be replayed straight back
+//! through the same defragmentation path it was recorded from.
+//!
+//! File format, one record after another:
+//!   timestamp_ms: u64 LE (monotonic, relative to when the tap was opened)
+//!   direction:    u8   (0 = host to chip, 1 = chip to host)
+//!   length:       u32 LE
+//!   data:         `length` bytes
+
+use crate::error::UwbErr;
+use android_hardware_uwb::aidl::android::hardware::uwb::IUwbClientCallback::IUwbClientCallbackAsyncServer;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::Instant;
+
+type Result<T> = std::result::Result<T, UwbErr>;
+
+/// Direction a tapped buffer travelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapDirection {
+    /// Host to chip (an outbound `send_uci_message` fragment).
+    HostToChip,
+    /// Chip to host (an inbound `onUciMessage` buffer).
+    ChipToHost,
+}
+
+/// Writes every tapped buffer to `path` in the framed format above.
+pub struct UciTap {
+    file: File,
+    start: Instant,
+}
+
+impl UciTap {
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(UciTap { file: File::create(path)?, start: Instant::now() })
+    }
+
+    pub fn record(&mut self, direction: TapDirection, data: &[u8]) -> Result<()> {
+        let timestamp_ms = self.start.elapsed().as_millis() as u64;
+        let direction_byte: u8 = match direction {
+            TapDirection::HostToChip => 0,
+            TapDirection::ChipToHost => 1,
+        };
+        self.file.write_all(&timestamp_ms.to_le_bytes())?;
+        self.file.write_all(&[direction_byte])?;
+        self.file.write_all(&(data.len() as u32).to_le_bytes())?;
+        self.file.write_all(data)?;
+        Ok(())
+    }
+}
+
+/// One decoded record from a tap file.
+#[derive(Debug, Clone)]
+pub struct TapRecord {
+    pub timestamp_ms: u64,
+    pub direction: TapDirection,
+    pub data: Vec<u8>,
+}
+
+/// Reads every record out of a tap file written by [`UciTap`], in order.
+pub fn read_tap(path: &Path) -> Result<Vec<TapRecord>> {
+    let mut file = File::open(path)?;
+    let mut records = Vec::new();
+    loop {
+        let mut timestamp_bytes = [0u8; 8];
+        match file.read_exact(&mut timestamp_bytes) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        let mut direction_byte = [0u8; 1];
+        file.read_exact(&mut direction_byte)?;
+        let direction = match direction_byte[0] {
+            0 => TapDirection::HostToChip,
+            _ => TapDirection::ChipToHost,
+        };
+        let mut length_bytes = [0u8; 4];
+        file.read_exact(&mut length_bytes)?;
+        let mut data = vec![0u8; u32::from_le_bytes(length_bytes) as usize];
+        file.read_exact(&mut data)?;
+        records.push(TapRecord {
+            timestamp_ms: u64::from_le_bytes(timestamp_bytes),
+            direction,
+            data,
+        });
+    }
+    Ok(records)
+}
+
+/// Replays every `ChipToHost` record from a tap file straight through
+/// `callback.onUciMessage`, in order, reproducing the defragmentation path
+/// exactly as it ran on the device the capture came from. Captured field
+/// traces can be dropped in as fixtures and driven through this instead of
+/// hand-written fragment arrays.
+pub async fn replay_chip_to_host(
+    path: &Path,
+    callback: &super::UwbClientCallback,
+) -> Result<()> {
+    for record in read_tap(path)? {
+        if record.direction == TapDirection::ChipToHost {
+            callback
+                .onUciMessage(&record.data)
+                .await
+                .map_err(|e| UwbErr::Specialized(format!("replay of {:?} failed: {:?}", path, e)))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_records_in_order() {
+        let path = std::env::temp_dir().join(format!("uci_tap_test_{:?}", std::thread::current().id()));
+        let mut tap = UciTap::open(&path).unwrap();
+        tap.record(TapDirection::HostToChip, &[0x20, 0x01, 0x00, 0x00]).unwrap();
+        tap.record(TapDirection::ChipToHost, &[0x40, 0x01, 0x00, 0x01, 0x00]).unwrap();
+        drop(tap);
+
+        let records = read_tap(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].direction, TapDirection::HostToChip);
+        assert_eq!(records[0].data, vec![0x20, 0x01, 0x00, 0x00]);
+        assert_eq!(records[1].direction, TapDirection::ChipToHost);
+        assert_eq!(records[1].data, vec![0x40, 0x01, 0x00, 0x01, 0x00]);
+        assert!(records[1].timestamp_ms >= records[0].timestamp_ms);
+    }
+
+    #[test]
+    fn empty_file_has_no_records() {
+        let path = std::env::temp_dir().join(format!("uci_tap_test_empty_{:?}", std::thread::current().id()));
+        UciTap::open(&path).unwrap();
+        let records = read_tap(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(records.is_empty());
+    }
+}
@@ -0,0 +1,15 @@
+//! CLI wrapper around `adaptation::disassemble::run_cli`: joins argv into a
+//! whitespace-separated hex string, disassembles it, and prints the result.
+//!
+//!     uci-disasm 60 01 00 01 01
+//!
+//! `#[path]`-includes the module directly rather than depending on this
+//! workspace's library crate name, which isn't fixed by anything in this
+//! checkout (no `Cargo.toml`/`Android.bp` is present alongside `src/rust`).
+
+#[path = "../adaptation/disassemble.rs"]
+mod disassemble;
+
+fn main() {
+    println!("{}", disassemble::run_cli(std::env::args().skip(1)));
+}